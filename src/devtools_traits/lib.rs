@@ -0,0 +1,44 @@
+//! Messages sent to the devtools actor so it can mirror what the network
+//! stack is doing in the Network panel.
+
+extern crate hyper;
+extern crate url;
+
+use hyper::header::Headers;
+use hyper::http::RawStatus;
+use hyper::method::Method;
+use std::sync::mpsc::Sender;
+use url::Url;
+
+#[derive(Clone, Debug)]
+pub struct HttpRequest {
+    pub url: Url,
+    pub method: Method,
+    pub headers: Headers,
+    pub body: Option<Vec<u8>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct HttpResponse {
+    pub headers: Option<Headers>,
+    pub status: Option<RawStatus>,
+    pub body: Option<Vec<u8>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum NetworkEvent {
+    HttpRequest(HttpRequest),
+    HttpResponse(HttpResponse),
+}
+
+#[derive(Clone, Debug)]
+pub enum ChromeToDevtoolsControlMsg {
+    NetworkEvent(String, NetworkEvent),
+}
+
+#[derive(Clone, Debug)]
+pub enum DevtoolsControlMsg {
+    FromChrome(ChromeToDevtoolsControlMsg),
+}
+
+pub type DevtoolsControlChan = Sender<DevtoolsControlMsg>;
@@ -0,0 +1,27 @@
+// Fixed-point "app units" (1/60th of a CSS pixel), and a minimal 2D size
+// type. Both are used throughout layout in the real tree; this snapshot
+// only needs the conversions that the reflected `width`/`height` IDL
+// attributes round-trip through.
+
+const AU_PER_PX: int = 60;
+
+struct Au(int);
+
+fn au_to_px(au: Au) -> int {
+    *au / AU_PER_PX
+}
+
+fn px_to_au(px: int) -> Au {
+    Au(px * AU_PER_PX)
+}
+
+struct Size2D<T> {
+    width: T,
+    height: T,
+}
+
+impl<T: Copy> Size2D<T> {
+    static fn new(width: T, height: T) -> Size2D<T> {
+        Size2D { width: width, height: height }
+    }
+}
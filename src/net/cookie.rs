@@ -0,0 +1,25 @@
+//! A single stored cookie, wrapping the `cookie_rs` parse result with the
+//! origin information `CookieStorage` needs to decide which requests it
+//! applies to.
+
+extern crate cookie as cookie_rs;
+
+use net_traits::CookieSource;
+use url::Url;
+
+#[derive(Clone)]
+pub struct Cookie {
+    pub cookie: cookie_rs::Cookie,
+    pub host: String,
+    pub host_only: bool,
+}
+
+impl Cookie {
+    pub fn new_wrapped(cookie: cookie_rs::Cookie, url: &Url, _source: CookieSource) -> Option<Cookie> {
+        let host = match url.domain() {
+            Some(domain) => domain.to_string(),
+            None => return None,
+        };
+        Some(Cookie { cookie: cookie, host: host, host_only: true })
+    }
+}
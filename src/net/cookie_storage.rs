@@ -0,0 +1,34 @@
+//! The cookie jar `load()` reads from before sending a request and writes
+//! to after receiving a `Set-Cookie` response.
+
+use cookie::Cookie;
+use net_traits::CookieSource;
+use url::Url;
+
+pub struct CookieStorage {
+    cookies: Vec<Cookie>,
+}
+
+impl CookieStorage {
+    pub fn new() -> CookieStorage {
+        CookieStorage { cookies: Vec::new() }
+    }
+
+    pub fn push(&mut self, cookie: Cookie, _source: CookieSource) {
+        self.cookies.retain(|c| c.cookie.name != cookie.cookie.name || c.host != cookie.host);
+        self.cookies.push(cookie);
+    }
+
+    pub fn cookies_for_url(&mut self, url: &Url, _source: CookieSource) -> Option<String> {
+        let host = url.domain().unwrap_or("");
+        let matching: Vec<String> = self.cookies.iter()
+            .filter(|c| c.host == host)
+            .map(|c| format!("{}={}", c.cookie.name, c.cookie.value))
+            .collect();
+        if matching.is_empty() {
+            None
+        } else {
+            Some(matching.join("; "))
+        }
+    }
+}
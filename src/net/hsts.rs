@@ -0,0 +1,29 @@
+//! A minimal HSTS (HTTP Strict Transport Security) host list: which hosts
+//! have told us, via a `Strict-Transport-Security` response header over
+//! HTTPS, that they should only ever be loaded over HTTPS.
+
+use std::collections::HashMap;
+
+pub struct HSTSList {
+    hosts: HashMap<String, u64>,
+}
+
+impl HSTSList {
+    pub fn new() -> HSTSList {
+        HSTSList { hosts: HashMap::new() }
+    }
+
+    pub fn is_host_secure(&self, host: &str) -> bool {
+        self.hosts.contains_key(host)
+    }
+
+    // `max_age: 0` is the spec's way of saying "forget this host",
+    // everything else (re-)adds it.
+    pub fn update_from_header(&mut self, host: &str, max_age_seconds: u64) {
+        if max_age_seconds == 0 {
+            self.hosts.remove(host);
+        } else {
+            self.hosts.insert(host.to_string(), max_age_seconds);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! A minimal HTTP response cache: enough `Cache-Control` parsing to know
+//! when a cached response is still fresh, and enough `ETag`/`Last-Modified`
+//! bookkeeping to revalidate it with a conditional request once it isn't.
+
+use hyper::header::Headers;
+use std::collections::HashMap;
+use std::time::Instant;
+use url::Url;
+
+struct CacheEntry {
+    body: Vec<u8>,
+    stored_at: Instant,
+    max_age: Option<u64>,
+    // `no-cache` permits storing the response but forbids serving it
+    // without revalidation first, unlike `no-store` which forbids storing
+    // it at all.
+    no_cache: bool,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+pub struct HttpCache {
+    entries: HashMap<Url, CacheEntry>,
+}
+
+impl HttpCache {
+    pub fn new() -> HttpCache {
+        HttpCache { entries: HashMap::new() }
+    }
+
+    /// The cached body for `url`, if a cached response exists, wasn't stored
+    /// with `no-cache`, and its `max-age` hasn't elapsed yet.
+    pub fn fresh_body(&self, url: &Url) -> Option<Vec<u8>> {
+        self.entries.get(url).and_then(|entry| {
+            if entry.no_cache {
+                return None;
+            }
+            let max_age = match entry.max_age {
+                Some(max_age) => max_age,
+                None => return None,
+            };
+            if entry.stored_at.elapsed().as_secs() < max_age {
+                Some(entry.body.clone())
+            } else {
+                None
+            }
+        })
+    }
+
+    /// The `If-None-Match`/`If-Modified-Since` values to revalidate the
+    /// cached entry for `url` with, if it has an `ETag` or `Last-Modified`
+    /// to revalidate against.
+    pub fn conditional_headers(&self, url: &Url) -> Option<(Option<String>, Option<String>)> {
+        self.entries.get(url).and_then(|entry| {
+            if entry.etag.is_none() && entry.last_modified.is_none() {
+                None
+            } else {
+                Some((entry.etag.clone(), entry.last_modified.clone()))
+            }
+        })
+    }
+
+    /// The body to serve for a `304 Not Modified` response to a conditional
+    /// request, i.e. the body stored from the last full response for `url`.
+    pub fn revalidated_body(&self, url: &Url) -> Option<Vec<u8>> {
+        self.entries.get(url).map(|entry| entry.body.clone())
+    }
+
+    pub fn store(&mut self, url: Url, headers: &Headers, body: Vec<u8>) {
+        let cache_control = headers.get_raw("Cache-Control")
+            .and_then(|v| v.get(0))
+            .map(|v| String::from_utf8_lossy(v).into_owned());
+
+        if let Some(ref cache_control) = cache_control {
+            if cache_control.split(',').any(|part| part.trim().eq_ignore_ascii_case("no-store")) {
+                self.entries.remove(&url);
+                return;
+            }
+        }
+
+        let max_age = cache_control.as_ref().and_then(|cache_control| parse_max_age(cache_control));
+        let no_cache = cache_control.as_ref()
+            .map(|cache_control| cache_control.split(',').any(|part| part.trim().eq_ignore_ascii_case("no-cache")))
+            .unwrap_or(false);
+        let etag = headers.get_raw("ETag").and_then(|v| v.get(0))
+            .map(|v| String::from_utf8_lossy(v).into_owned());
+        let last_modified = headers.get_raw("Last-Modified").and_then(|v| v.get(0))
+            .map(|v| String::from_utf8_lossy(v).into_owned());
+
+        self.entries.insert(url, CacheEntry {
+            body: body,
+            stored_at: Instant::now(),
+            max_age: max_age,
+            no_cache: no_cache,
+            etag: etag,
+            last_modified: last_modified,
+        });
+    }
+}
+
+fn parse_max_age(cache_control: &str) -> Option<u64> {
+    for part in cache_control.split(',') {
+        let part = part.trim();
+        if let Some(rest) = part.strip_prefix("max-age=") {
+            return rest.trim().parse().ok();
+        }
+    }
+    None
+}
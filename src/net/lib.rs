@@ -0,0 +1,10 @@
+//! The network stack: cookie storage, HSTS tracking, cross-origin request
+//! checks, image decoding, and the HTTP loader that ties them together.
+
+pub mod cookie;
+pub mod cookie_storage;
+pub mod cors;
+pub mod hsts;
+pub mod http_cache;
+pub mod http_loader;
+pub mod image;
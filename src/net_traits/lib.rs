@@ -0,0 +1,55 @@
+//! Types shared between the `net` crate and its callers (script, the
+//! resource-loading glue, and `net`'s own test suite) so that neither side
+//! has to reach into the other's internals.
+
+extern crate hyper;
+extern crate url;
+
+use hyper::header::Headers;
+use hyper::method::Method;
+use url::Url;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CookieSource {
+    HTTP,
+    NonHTTP,
+}
+
+/// https://w3c.github.io/webappsec-referrer-policy/#referrer-policies
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReferrerPolicy {
+    NoReferrer,
+    NoReferrerWhenDowngrade,
+    SameOrigin,
+    Origin,
+    OriginWhenCrossOrigin,
+    StrictOrigin,
+    StrictOriginWhenCrossOrigin,
+    UnsafeUrl,
+}
+
+#[derive(Clone)]
+pub struct LoadData {
+    pub url: Url,
+    pub method: Method,
+    pub headers: Headers,
+    pub data: Option<Vec<u8>>,
+    /// The policy to apply when deciding what, if anything, to send as the
+    /// `Referer` header. `None` means no referrer information is available
+    /// (e.g. the load isn't driven by a document), so no header is sent.
+    pub referrer_policy: Option<ReferrerPolicy>,
+    pub referrer_url: Option<Url>,
+}
+
+impl LoadData {
+    pub fn new(url: Url, data: Option<Vec<u8>>) -> LoadData {
+        LoadData {
+            url: url,
+            method: Method::Get,
+            headers: Headers::new(),
+            data: data,
+            referrer_policy: None,
+            referrer_url: None,
+        }
+    }
+}
@@ -0,0 +1,77 @@
+// The data backing every reflected DOM node: its position in the node
+// table (looked up through a `NodeScope`), the cached JS reflector for
+// that node, and - for elements - the tag-specific payload and attribute
+// table that the bindings layer reads and writes.
+
+import util::atom::Atom;
+import gfx::geometry::{Au, Size2D};
+import js::jsapi::JSObject;
+import std::map::HashMap;
+import url::Url;
+
+enum NodeKind {
+    Element(~ElementData),
+    Text(~str),
+    Comment(~str),
+    Doctype(~str),
+}
+
+// One entry per node. `reflector` caches the JS wrapper for this node so
+// that `element::create` returns the same object on every call instead of
+// minting a new one (see the comment there); it's cleared by
+// `element::finalize` once that wrapper is collected.
+struct NodeData {
+    kind: ~NodeKind,
+    reflector: Option<*JSObject>,
+}
+
+fn NodeData(kind: NodeKind) -> NodeData {
+    NodeData { kind: ~kind, reflector: None }
+}
+
+struct ElementData {
+    tag_name: ~str,
+    attrs: HashMap<Atom, ~str>,
+    kind: ~ElementKind,
+}
+
+fn ElementData(tag_name: ~str, kind: ElementKind) -> ElementData {
+    ElementData { tag_name: tag_name, attrs: HashMap(), kind: ~kind }
+}
+
+enum ElementKind {
+    HTMLDivElement,
+    HTMLHeadElement,
+    HTMLImageElement(HTMLImageElementData),
+    HTMLScriptElement,
+    UnknownElement,
+}
+
+struct HTMLImageElementData {
+    size: Size2D<Au>,
+}
+
+fn HTMLImageElementData() -> HTMLImageElementData {
+    HTMLImageElementData { size: Size2D::new(Au(0), Au(0)) }
+}
+
+// An opaque handle into a `NodeScope`'s node table. Reflectors only ever
+// hold this plus the owning `NodeScope`, never a `NodeData` directly, so a
+// node can be read and written from any number of wrappers without
+// aliasing `&mut NodeData` across calls.
+type Node = uint;
+
+struct NodeScope {
+    nodes: @mut ~[NodeData],
+    document_url: Url,
+}
+
+impl NodeScope {
+    fn write<R>(&self, node: Node, f: fn(&mut NodeData) -> R) -> R {
+        f(&mut self.nodes[node])
+    }
+
+    fn document_url(&self) -> Url {
+        copy self.document_url
+    }
+}
@@ -0,0 +1,86 @@
+// Conversions between Rust DOM value types and `jsval`s.
+//
+// Every accessor used to open-code its own `RUST_*_JSVAL`/`domstring_to_jsval`
+// dance. `ToJSValConvertible`/`FromJSValConvertible` centralize that so a
+// getter is just `value.to_jsval(cx, vp)` and a setter is just
+// `let value: T = from_jsval(cx, *vp);`.
+
+import js::jsapi::{JSContext, jsval};
+import js::JSVAL_NULL;
+import js::jsapi::bindgen::*;
+import js::glue::bindgen::*;
+import utils::{domstring_to_jsval, jsval_to_str, str, DOMString};
+import gfx::geometry::{Au, au_to_px, px_to_au};
+
+trait ToJSValConvertible {
+    fn to_jsval(&self, cx: *JSContext, vp: *mut jsval);
+}
+
+trait FromJSValConvertible {
+    static fn from_jsval(cx: *JSContext, val: jsval) -> Self;
+}
+
+impl i32: ToJSValConvertible {
+    fn to_jsval(&self, _cx: *JSContext, vp: *mut jsval) unsafe {
+        *vp = RUST_INT_TO_JSVAL(*self as libc::c_int);
+    }
+}
+
+impl i32: FromJSValConvertible {
+    static fn from_jsval(_cx: *JSContext, val: jsval) -> i32 unsafe {
+        RUST_JSVAL_TO_INT(val) as i32
+    }
+}
+
+impl bool: ToJSValConvertible {
+    fn to_jsval(&self, _cx: *JSContext, vp: *mut jsval) unsafe {
+        *vp = RUST_BOOLEAN_TO_JSVAL(*self as JSBool);
+    }
+}
+
+impl bool: FromJSValConvertible {
+    static fn from_jsval(_cx: *JSContext, val: jsval) -> bool unsafe {
+        RUST_JSVAL_TO_BOOLEAN(val) != 0
+    }
+}
+
+impl DOMString: ToJSValConvertible {
+    fn to_jsval(&self, cx: *JSContext, vp: *mut jsval) unsafe {
+        *vp = domstring_to_jsval(cx, copy *self);
+    }
+}
+
+impl DOMString: FromJSValConvertible {
+    static fn from_jsval(cx: *JSContext, val: jsval) -> DOMString unsafe {
+        str(jsval_to_str(cx, val))
+    }
+}
+
+impl Au: ToJSValConvertible {
+    // Reflected as CSS pixels, masked with `i32::max_value` so the result
+    // fits in a signed jsval int (values are masked, not clamped), matching
+    // what `HTMLImageElement_getWidth` did by hand before.
+    fn to_jsval(&self, _cx: *JSContext, vp: *mut jsval) unsafe {
+        *vp = RUST_INT_TO_JSVAL(
+            (au_to_px(*self) & (i32::max_value as int)) as libc::c_int);
+    }
+}
+
+impl Au: FromJSValConvertible {
+    static fn from_jsval(_cx: *JSContext, val: jsval) -> Au unsafe {
+        px_to_au(RUST_JSVAL_TO_INT(val) as int)
+    }
+}
+
+impl<T: ToJSValConvertible> Option<T>: ToJSValConvertible {
+    fn to_jsval(&self, cx: *JSContext, vp: *mut jsval) unsafe {
+        match *self {
+          Some(ref value) => value.to_jsval(cx, vp),
+          None => *vp = JSVAL_NULL
+        }
+    }
+}
+
+fn from_jsval<T: FromJSValConvertible>(cx: *JSContext, val: jsval) -> T {
+    FromJSValConvertible::from_jsval(cx, val)
+}
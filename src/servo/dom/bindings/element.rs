@@ -1,7 +1,8 @@
 import js::rust::{bare_compartment, methods, jsobj};
 import js::{JS_ARGV, JSCLASS_HAS_RESERVED_SLOTS, JSPROP_ENUMERATE, JSPROP_SHARED, JSVAL_NULL,
             JS_THIS_OBJECT, JS_SET_RVAL, JSPROP_NATIVE_ACCESSORS};
-import js::jsapi::{JSContext, jsval, JSObject, JSBool, jsid, JSClass, JSFreeOp, JSPropertySpec};
+import js::jsapi::{JSContext, jsval, JSObject, JSBool, jsid, JSClass, JSFreeOp, JSPropertySpec,
+                   JSFunctionSpec};
 import js::jsapi::bindgen::{JS_ValueToString, JS_GetStringCharsZAndLength, JS_ReportError,
                             JS_GetReservedSlot, JS_SetReservedSlot, JS_NewStringCopyN,
                             JS_DefineFunctions, JS_DefineProperty, JS_GetContextPrivate};
@@ -10,20 +11,37 @@ import js::glue::bindgen::*;
 import js::crust::{JS_PropertyStub, JS_StrictPropertyStub, JS_EnumerateStub, JS_ConvertStub};
 
 import dom::base::{Node, NodeScope, Element};
-import node::NodeBundle;
-import utils::{rust_box, squirrel_away_unique, get_compartment, domstring_to_jsval, str};
+import node::{NodeBundle, EventListener};
+import utils::{rust_box, squirrel_away_unique, get_compartment, domstring_to_jsval, str,
+               jsval_to_str, object_from_raw};
 import libc::c_uint;
 import ptr::null;
 import node::unwrap;
 import dom::base::{HTMLImageElement, HTMLScriptElement, HTMLHeadElement, HTMLDivElement,
                    UnknownElement};
 import gfx::geometry::{au_to_px, px_to_au};
+import util::atom::Atom;
+import conversions::{ToJSValConvertible, FromJSValConvertible, from_jsval};
+import url::Url;
+import net::image::fetch_image;
+import net::cors::cors_fetch;
 
 extern fn finalize(_fop: *JSFreeOp, obj: *JSObject) {
     #debug("element finalize!");
     unsafe {
         let val = JS_GetReservedSlot(obj, 0);
-        let _node: ~NodeBundle = unsafe::reinterpret_cast(RUST_JSVAL_TO_PRIVATE(val));
+        // `node` is the same `~NodeBundle` throughout this function, so both
+        // halves of its teardown go through `.payload` the same way the rest
+        // of this file does.
+        let node: ~NodeBundle = unsafe::reinterpret_cast(RUST_JSVAL_TO_PRIVATE(val));
+        let rt = JS_GetObjectRuntime(obj);
+        for node.payload.listeners.each |_type, callbacks| {
+            for callbacks.each |callback| {
+                JS_RemoveValueRootRT(rt, ptr::to_unsafe_ptr(&callback.val));
+            }
+        }
+        // Let a future `create` call for this node mint a new wrapper.
+        node.payload.scope.write(node.payload.node, |nd| nd.reflector = None);
     }
 }
 
@@ -40,6 +58,24 @@ fn init(compartment: bare_compartment) {
         JS_DefineProperties(compartment.cx.ptr, obj.ptr, specs);
     });
 
+    let methods = @~[
+        {name: compartment.add_name(~"getAttribute"),
+         call: getAttribute, nargs: 1, flags: 0},
+        {name: compartment.add_name(~"setAttribute"),
+         call: setAttribute, nargs: 2, flags: 0},
+        {name: compartment.add_name(~"removeAttribute"),
+         call: removeAttribute, nargs: 1, flags: 0},
+        {name: compartment.add_name(~"hasAttribute"),
+         call: hasAttribute, nargs: 1, flags: 0},
+        {name: compartment.add_name(~"addEventListener"),
+         call: addEventListener, nargs: 2, flags: 0},
+        {name: compartment.add_name(~"removeEventListener"),
+         call: removeEventListener, nargs: 2, flags: 0}];
+    vec::push(compartment.global_props, methods);
+    vec::as_buf(*methods, |specs, _len| {
+        JS_DefineFunctions(compartment.cx.ptr, obj.ptr, specs);
+    });
+
     compartment.register_class(utils::instance_jsclass(~"GenericElementInstance",
                                                        finalize));
 
@@ -54,7 +90,12 @@ fn init(compartment: bare_compartment) {
          tinyid: 0,
          flags: (JSPROP_SHARED | JSPROP_ENUMERATE | JSPROP_NATIVE_ACCESSORS) as u8,
          getter: {op: HTMLImageElement_getWidth, info: null()},
-         setter: {op: HTMLImageElement_setWidth, info: null()}}];
+         setter: {op: HTMLImageElement_setWidth, info: null()}},
+        {name: compartment.add_name(~"src"),
+         tinyid: 0,
+         flags: (JSPROP_SHARED | JSPROP_ENUMERATE | JSPROP_NATIVE_ACCESSORS) as u8,
+         getter: {op: HTMLImageElement_getSrc, info: null()},
+         setter: {op: HTMLImageElement_setSrc, info: null()}}];
     vec::push(compartment.global_props, attrs);
     vec::as_buf(*attrs, |specs, _len| {
         JS_DefineProperties(compartment.cx.ptr, obj.ptr, specs);
@@ -80,8 +121,7 @@ extern fn HTMLImageElement_getWidth(cx: *JSContext, _argc: c_uint, vp: *mut jsva
           _ => fail ~"why is this not an element?"
         }
     });
-    *vp = RUST_INT_TO_JSVAL(
-              (au_to_px(width) & (i32::max_value as int)) as libc::c_int);
+    width.to_jsval(cx, vp);
     return 1;
 }
 
@@ -92,13 +132,13 @@ extern fn HTMLImageElement_setWidth(cx: *JSContext, _argc: c_uint, vp: *mut jsva
         return 0;
     }
 
+    let width = from_jsval(cx, *vp);
     let bundle = unwrap(obj);
     do (*bundle).payload.scope.write((*bundle).payload.node) |nd| {
         match nd.kind {
           ~Element(ed) => {
             match ed.kind {
-              ~HTMLImageElement(img) =>
-                img.size.width = px_to_au(RUST_JSVAL_TO_INT(*vp) as int),
+              ~HTMLImageElement(img) => img.size.width = width,
               _ => fail ~"why is this not an image element?"
             }
           }
@@ -108,6 +148,97 @@ extern fn HTMLImageElement_setWidth(cx: *JSContext, _argc: c_uint, vp: *mut jsva
     return 1;
 }
 
+extern fn HTMLImageElement_getSrc(cx: *JSContext, _argc: c_uint, vp: *mut jsval)
+    -> JSBool unsafe {
+    let obj = JS_THIS_OBJECT(cx, unsafe::reinterpret_cast(vp));
+    if obj.is_null() {
+        return 0;
+    }
+
+    let bundle = unwrap(obj);
+    do (*bundle).payload.scope.write((*bundle).payload.node) |nd| {
+        match nd.kind {
+          ~Element(ed) => {
+            match ed.attrs.find(&Atom::from_slice("src")) {
+              Some(value) => *vp = domstring_to_jsval(cx, str(copy *value)),
+              None => *vp = JSVAL_NULL
+            }
+          }
+          _ => fail ~"why is this not an element?"
+        }
+    };
+    return 1;
+}
+
+extern fn HTMLImageElement_setSrc(cx: *JSContext, _argc: c_uint, vp: *mut jsval)
+    -> JSBool unsafe {
+    let obj = JS_THIS_OBJECT(cx, unsafe::reinterpret_cast(vp));
+    if obj.is_null() {
+        return 0;
+    }
+
+    let src: DOMString = from_jsval(cx, *vp);
+    let bundle = unwrap(obj);
+    let crossorigin = (*bundle).payload.scope.write((*bundle).payload.node, |nd| {
+        match nd.kind {
+          ~Element(ed) => {
+            ed.attrs.insert(Atom::from_slice("src"), copy src);
+            ed.attrs.find(&Atom::from_slice("crossorigin")).map(|v| copy *v)
+          }
+          _ => fail ~"why is this not an element?"
+        }
+    });
+    load_image_src(cx, obj, bundle, src, crossorigin);
+    return 1;
+}
+
+// Kicks off the fetch for a newly-set `src`, taking `crossorigin` into
+// account, and stores the decoded intrinsic size back onto the element so
+// that `HTMLImageElement_getWidth` reports it once loaded. A failed fetch
+// is surfaced to script as an `error` event rather than failing silently.
+fn load_image_src(cx: *JSContext, obj: *JSObject, bundle: *rust_box<NodeBundle>,
+                   src: DOMString, crossorigin: Option<DOMString>) unsafe {
+    let document_url = (*bundle).payload.scope.document_url();
+    let image_url = match Url::parse(*src) {
+      Ok(u) => u,
+      Err(*) => {
+        dispatch_event(cx, obj, (*bundle).payload.node, (*bundle).payload.scope,
+                       Atom::from_slice("error"));
+        return;
+      }
+    };
+
+    // Without a `crossorigin` attribute, `<img src>` is a plain no-cors
+    // fetch: it succeeds regardless of cross-origin response headers, same
+    // as any other cross-origin image on the web. CORS enforcement only
+    // kicks in once the page opts in with `crossorigin`.
+    let result = if crossorigin.is_none() {
+        fetch_image(&image_url)
+    } else {
+        cors_fetch(&document_url, &image_url, crossorigin)
+    };
+
+    match result {
+      Ok(image) => {
+        (*bundle).payload.scope.write((*bundle).payload.node, |nd| {
+            match nd.kind {
+              ~Element(ed) => {
+                match ed.kind {
+                  ~HTMLImageElement(img) => img.size = image.size,
+                  _ => fail ~"why is this not an image element?"
+                }
+              }
+              _ => fail ~"why is this not an element?"
+            }
+        });
+      }
+      Err(*) => {
+        dispatch_event(cx, obj, (*bundle).payload.node, (*bundle).payload.scope,
+                       Atom::from_slice("error"));
+      }
+    }
+}
+
 extern fn getTagName(cx: *JSContext, _argc: c_uint, vp: *mut jsval)
     -> JSBool {
     unsafe {
@@ -120,8 +251,7 @@ extern fn getTagName(cx: *JSContext, _argc: c_uint, vp: *mut jsval)
         do (*bundle).payload.scope.write((*bundle).payload.node) |nd| {
             match nd.kind {
               ~Element(ed) => {
-                let s = str(copy ed.tag_name);
-                *vp = domstring_to_jsval(cx, s);
+                str(copy ed.tag_name).to_jsval(cx, vp);
               }
               _ => {
                 //XXXjdm should probably read the spec to figure out what to do here
@@ -133,7 +263,191 @@ extern fn getTagName(cx: *JSContext, _argc: c_uint, vp: *mut jsval)
     return 1;
 }
 
+// Reads argument `n` off of `vp` and interns it as an atom, so that every
+// reflected attribute name is a pointer-equality comparison against the
+// same shared string rather than a fresh ~str allocation per call.
+unsafe fn attr_name_arg(cx: *JSContext, vp: *mut jsval, n: uint) -> Atom {
+    let argv = JS_ARGV(cx, unsafe::reinterpret_cast(vp));
+    let name = jsval_to_str(cx, *ptr::offset(argv, n));
+    Atom::from_slice(name)
+}
+
+extern fn getAttribute(cx: *JSContext, argc: c_uint, vp: *mut jsval)
+    -> JSBool unsafe {
+    let obj = JS_THIS_OBJECT(cx, unsafe::reinterpret_cast(vp));
+    if obj.is_null() {
+        return 0;
+    }
+
+    let name = attr_name_arg(cx, vp, 0);
+    let bundle = unwrap(obj);
+    do (*bundle).payload.scope.write((*bundle).payload.node) |nd| {
+        match nd.kind {
+          ~Element(ed) => {
+            match ed.attrs.find(&name) {
+              Some(value) => *vp = domstring_to_jsval(cx, str(copy *value)),
+              None => *vp = JSVAL_NULL
+            }
+          }
+          _ => fail ~"why is this not an element?"
+        }
+    };
+    return 1;
+}
+
+extern fn setAttribute(cx: *JSContext, argc: c_uint, vp: *mut jsval)
+    -> JSBool unsafe {
+    let obj = JS_THIS_OBJECT(cx, unsafe::reinterpret_cast(vp));
+    if obj.is_null() {
+        return 0;
+    }
+
+    let name = attr_name_arg(cx, vp, 0);
+    let argv = JS_ARGV(cx, unsafe::reinterpret_cast(vp));
+    let value = jsval_to_str(cx, *ptr::offset(argv, 1));
+    let bundle = unwrap(obj);
+    do (*bundle).payload.scope.write((*bundle).payload.node) |nd| {
+        match nd.kind {
+          ~Element(ed) => { ed.attrs.insert(name, value); }
+          _ => fail ~"why is this not an element?"
+        }
+    };
+    return 1;
+}
+
+extern fn removeAttribute(cx: *JSContext, argc: c_uint, vp: *mut jsval)
+    -> JSBool unsafe {
+    let obj = JS_THIS_OBJECT(cx, unsafe::reinterpret_cast(vp));
+    if obj.is_null() {
+        return 0;
+    }
+
+    let name = attr_name_arg(cx, vp, 0);
+    let bundle = unwrap(obj);
+    do (*bundle).payload.scope.write((*bundle).payload.node) |nd| {
+        match nd.kind {
+          ~Element(ed) => { ed.attrs.remove(&name); }
+          _ => fail ~"why is this not an element?"
+        }
+    };
+    return 1;
+}
+
+extern fn hasAttribute(cx: *JSContext, argc: c_uint, vp: *mut jsval)
+    -> JSBool unsafe {
+    let obj = JS_THIS_OBJECT(cx, unsafe::reinterpret_cast(vp));
+    if obj.is_null() {
+        return 0;
+    }
+
+    let name = attr_name_arg(cx, vp, 0);
+    let bundle = unwrap(obj);
+    let found = (*bundle).payload.scope.write((*bundle).payload.node, |nd| {
+        match nd.kind {
+          ~Element(ed) => ed.attrs.contains_key(&name),
+          _ => fail ~"why is this not an element?"
+        }
+    });
+    *vp = RUST_BOOLEAN_TO_JSVAL(found as JSBool);
+    return 1;
+}
+
+extern fn addEventListener(cx: *JSContext, argc: c_uint, vp: *mut jsval)
+    -> JSBool unsafe {
+    let obj = JS_THIS_OBJECT(cx, unsafe::reinterpret_cast(vp));
+    if obj.is_null() {
+        return 0;
+    }
+
+    let argv = JS_ARGV(cx, unsafe::reinterpret_cast(vp));
+    let callback = *ptr::offset(argv, 1);
+    if JS_TypeOfValue(cx, callback) != JSTYPE_FUNCTION
+       || JS_ObjectIsCallable(cx, RUST_JSVAL_TO_OBJECT(callback)) == 0 {
+        JS_ReportError(cx, ~"addEventListener: callback is not callable");
+        return 0;
+    }
+
+    let event_type = attr_name_arg(cx, vp, 0);
+    let bundle = unwrap(obj);
+    (*bundle).payload.listeners.insert_or_update_with(event_type, ~[EventListener { val: callback }],
+        |_, existing| existing.push(EventListener { val: callback }));
+    // Root the listener's heap-stored `val` field now that it actually lives
+    // in `(*bundle).payload.listeners`, not the transient `callback` copy on
+    // this stack frame, which is gone as soon as this function returns.
+    match (*bundle).payload.listeners.find_mut(&event_type) {
+      Some(callbacks) => {
+        let last = callbacks.len() - 1;
+        JS_AddValueRoot(cx, ptr::to_unsafe_ptr(&callbacks[last].val));
+      }
+      None => {}
+    }
+    return 1;
+}
+
+extern fn removeEventListener(cx: *JSContext, argc: c_uint, vp: *mut jsval)
+    -> JSBool unsafe {
+    let obj = JS_THIS_OBJECT(cx, unsafe::reinterpret_cast(vp));
+    if obj.is_null() {
+        return 0;
+    }
+
+    let argv = JS_ARGV(cx, unsafe::reinterpret_cast(vp));
+    let callback = *ptr::offset(argv, 1);
+    let event_type = attr_name_arg(cx, vp, 0);
+    let bundle = unwrap(obj);
+    match (*bundle).payload.listeners.find_mut(&event_type) {
+      Some(callbacks) => {
+        let rt = JS_GetObjectRuntime(obj);
+        callbacks.retain(|l| {
+            if RUST_SAME_VALUE(l.val, callback) {
+                JS_RemoveValueRootRT(rt, ptr::to_unsafe_ptr(&l.val));
+                false
+            } else {
+                true
+            }
+        });
+      }
+      None => {}
+    }
+    return 1;
+}
+
+// Invokes every listener registered for `event_type` on `node`, passing a
+// freshly reflected Event object as the sole argument. Called internally by
+// the event-dispatch subsystem, not exposed directly to script.
+fn dispatch_event(cx: *JSContext, obj: *JSObject, node: Node, scope: NodeScope,
+                   event_type: Atom) unsafe {
+    let bundle = unwrap(obj);
+    let callbacks = match (*bundle).payload.listeners.find(&event_type) {
+      Some(callbacks) => copy *callbacks,
+      None => return
+    };
+
+    let compartment = utils::get_compartment(cx);
+    let event = result::unwrap(
+        (*compartment).new_object_with_proto(~"Event", ~"Event",
+                                             (*compartment).global_obj.ptr));
+
+    let argv = ~[RUST_OBJECT_TO_JSVAL(event.ptr)];
+    for callbacks.each |listener| {
+        let mut rval = JSVAL_NULL;
+        vec::as_buf(argv, |argv_buf, _len| {
+            JS_CallFunctionValue(cx, obj, listener.val, 1, argv_buf, ptr::to_mut_unsafe_ptr(&mut rval));
+        });
+    }
+}
+
 fn create(cx: *JSContext, node: Node, scope: NodeScope) -> jsobj unsafe {
+    // Every node gets at most one live reflector: returning a fresh wrapper
+    // on each call would break JS object identity (`a === a`) and leak a
+    // NodeBundle per call. The cached pointer lives on the node itself and
+    // is cleared by `finalize` once its wrapper is collected.
+    let cached = scope.write(node, |nd| nd.reflector);
+    match cached {
+      Some(existing) => return object_from_raw(cx, existing),
+      None => {}
+    }
+
     let proto = scope.write(node, |nd| {
         match nd.kind {
           ~Element(ed) => {
@@ -147,7 +461,7 @@ fn create(cx: *JSContext, node: Node, scope: NodeScope) -> jsobj unsafe {
           }
           _ => fail ~"element::create only handles elements"
         }
-    });   
+    });
 
     //XXXjdm the parent should probably be the node parent instead of the global
     //TODO error checking
@@ -155,11 +469,12 @@ fn create(cx: *JSContext, node: Node, scope: NodeScope) -> jsobj unsafe {
     let obj = result::unwrap(
         (*compartment).new_object_with_proto(~"GenericElementInstance", proto,
                                              (*compartment).global_obj.ptr));
- 
+
     unsafe {
         let raw_ptr: *libc::c_void =
             unsafe::reinterpret_cast(squirrel_away_unique(~NodeBundle(node, scope)));
         JS_SetReservedSlot(obj.ptr, 0, RUST_PRIVATE_TO_JSVAL(raw_ptr));
     }
+    scope.write(node, |nd| nd.reflector = Some(obj.ptr));
     return obj;
 }
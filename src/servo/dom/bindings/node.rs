@@ -0,0 +1,38 @@
+// The private data stashed in a reflector's reserved slot: the `Node`
+// handle and `NodeScope` needed to look up that node's `NodeData`.
+
+import js::jsapi::{JSContext, JSObject, jsval};
+import js::jsapi::bindgen::{JS_GetReservedSlot, JS_SetReservedSlot};
+import js::glue::bindgen::*;
+import dom::base::{Node, NodeScope};
+import util::atom::Atom;
+import utils::rust_box;
+import std::map::HashMap;
+
+// A single rooted callback registered via addEventListener. Kept rooted for
+// the lifetime of the registration and unrooted in `element::finalize`
+// alongside the rest of the NodeBundle teardown.
+struct EventListener {
+    val: jsval
+}
+
+struct NodePayload {
+    node: Node,
+    scope: NodeScope,
+    listeners: HashMap<Atom, ~[EventListener]>,
+}
+
+struct NodeBundle {
+    payload: NodePayload,
+}
+
+fn NodeBundle(node: Node, scope: NodeScope) -> NodeBundle {
+    NodeBundle { payload: NodePayload { node: node, scope: scope, listeners: HashMap() } }
+}
+
+// Pulls the `NodeBundle` a reflector was created with back out of its
+// reserved slot. Every accessor in `element.rs` starts here.
+fn unwrap(obj: *JSObject) -> *rust_box<NodeBundle> unsafe {
+    let val = JS_GetReservedSlot(obj, 0);
+    unsafe::reinterpret_cast(RUST_JSVAL_TO_PRIVATE(val))
+}
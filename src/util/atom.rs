@@ -0,0 +1,51 @@
+// Interned strings for attribute and event-type names.
+//
+// Every `ed.attrs.find(&Atom::from_slice("src"))` lookup used to hash and
+// compare a fresh `~str`. `Atom` interns the string once per unique value
+// and compares by pointer afterwards, so repeated lookups of the same name
+// (which is the common case - "src", "class", "id", ...) are cheap.
+
+import std::map::HashMap;
+
+struct Atom(@str);
+
+impl Atom: cmp::Eq {
+    pure fn eq(&self, other: &Atom) -> bool {
+        ptr::ref_eq(**self, **other)
+    }
+    pure fn ne(&self, other: &Atom) -> bool { !self.eq(other) }
+}
+
+impl Atom: to_bytes::IterBytes {
+    pure fn iter_bytes(&self, lsb0: bool, f: to_bytes::Cb) {
+        ptr::to_unsafe_ptr(&**self).iter_bytes(lsb0, f)
+    }
+}
+
+// One intern table per script task. There is exactly one script task per
+// process in this snapshot, so a lazily-initialized global is enough - a
+// real multi-task build would thread this through `NodeScope` instead.
+fn intern_table() -> @mut HashMap<~str, @str> {
+    unsafe {
+        if TABLE.is_none() {
+            TABLE = Some(@mut std::map::HashMap());
+        }
+        option::get(TABLE)
+    }
+}
+
+static mut TABLE: Option<@mut HashMap<~str, @str>> = None;
+
+impl Atom {
+    static fn from_slice(s: &str) -> Atom {
+        let table = intern_table();
+        match table.find(&s.to_str()) {
+          Some(existing) => Atom(existing),
+          None => {
+            let interned: @str = @s.to_str();
+            table.insert(s.to_str(), interned);
+            Atom(interned)
+          }
+        }
+    }
+}